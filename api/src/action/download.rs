@@ -1,25 +1,40 @@
-use std::fs::File;
+use std::ffi::OsString;
+use std::fs::{self, File};
 use std::io::{
     self,
     Error as IoError,
     Read,
+    Write,
 };
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, Mutex};
-
+use std::time::Duration;
+
+// `fs2` (disk space checks) and `zip` (archive extraction) are workspace
+// dependencies this module needs declared in `api`'s manifest, same as the
+// `openssl` and `reqwest` crates already used below.
+use fs2;
+use openssl::hash::MessageDigest;
+use openssl::pkcs5::pbkdf2_hmac;
 use openssl::symm::decrypt_aead;
 use reqwest::{
-    Client, 
+    Client,
     Error as ReqwestError,
+    RedirectPolicy,
     Response,
+    StatusCode,
 };
-use reqwest::header::Authorization;
-use reqwest::header::ContentLength;
+use reqwest::header::{Authorization, ContentLength};
 use serde_json;
+use zip::ZipArchive;
+use zip::result::ZipError;
 
 use crypto::b64;
 use crypto::key_set::KeySet;
 use crypto::sign::signature_encoded;
 use file::file::DownloadFile;
+// `Metadata::is_archive` is a new accessor this module relies on, to be
+// added alongside `name()` and `iv()` in the `file` module.
 use file::metadata::Metadata;
 use reader::{EncryptedFileWriter, ProgressReporter, ProgressWriter};
 
@@ -29,12 +44,82 @@ type StdResult<T, E> = ::std::result::Result<T, E>;
 /// The name of the header that is used for the authentication nonce.
 const HEADER_AUTH_NONCE: &'static str = "WWW-Authenticate";
 
+/// The number of PBKDF2 iterations to use when deriving the authentication
+/// key from a user-supplied password.
+const PASSWORD_AUTH_KEY_ITERATIONS: usize = 100;
+
+/// The length, in bytes, of the PBKDF2-derived password authentication key.
+const PASSWORD_AUTH_KEY_LENGTH: usize = 64;
+
+/// How long to wait for a single request to complete before giving up.
+const CLIENT_REQUEST_TIMEOUT: u64 = 90;
+
+/// The maximum number of redirects to follow, such as the storage redirect
+/// Send's download endpoint returns.
+const CLIENT_MAX_REDIRECTS: usize = 10;
+
 // TODO: experiment with `iv` of `None` in decrypt logic
 
+/// Build a `reqwest` client that is resilient against the kind of transient
+/// and redirecting behavior the Send API exhibits.
+///
+/// The client times out requests that hang, and follows a bounded number of
+/// redirects so the storage redirect returned by the download endpoint is
+/// handled transparently. Every redirect hop, not just the initial file URL,
+/// is required to stay on HTTPS; a hop that downgrades to an insecure scheme
+/// is stopped rather than followed, which surfaces as a non-2xx response to
+/// the caller.
+///
+/// No connection-pool idle timeout is set, and there is no builder-level
+/// HTTPS-only switch; HTTPS is enforced through the redirect policy above,
+/// together with the scheme check on the file URL in `Download::invoke`.
+pub fn client() -> StdResult<Client, ReqwestError> {
+    Client::builder()
+        .timeout(Duration::from_secs(CLIENT_REQUEST_TIMEOUT))
+        .redirect(RedirectPolicy::custom(|attempt| {
+            if attempt.previous().len() >= CLIENT_MAX_REDIRECTS {
+                return attempt.stop();
+            }
+
+            if attempt.url().scheme() == "https" {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        }))
+        .build()
+}
+
 /// A file upload action to a Send server.
 pub struct Download<'a> {
     /// The Send file to download.
     file: &'a DownloadFile,
+
+    /// An optional password to use for authentication, if the file is
+    /// password protected.
+    password: Option<String>,
+
+    /// The target to write the downloaded file to, as given through the
+    /// `--output` CLI argument.
+    ///
+    /// If `None`, the file is written to the current directory using the
+    /// name from the file's metadata.
+    target: Option<PathBuf>,
+
+    /// Whether to skip downloading if the target already exists from a
+    /// previous attempt, as set through the `--continue` CLI flag.
+    ///
+    /// AES-GCM's authentication tag covers the entire ciphertext, so a
+    /// partially downloaded file can not be resumed at the byte level; a
+    /// stale partial download is discarded and restarted instead.
+    resume: bool,
+
+    /// Whether to always extract the downloaded file as an archive into the
+    /// target directory, as set through the `--extract` CLI flag.
+    ///
+    /// If not set, the file is still extracted automatically when its
+    /// metadata marks it as an archive and the target is a directory.
+    extract: bool,
 }
 
 impl<'a> Download<'a> {
@@ -42,15 +127,56 @@ impl<'a> Download<'a> {
     pub fn new(file: &'a DownloadFile) -> Self {
         Self {
             file,
+            password: None,
+            target: None,
+            resume: false,
+            extract: false,
         }
     }
 
+    /// Set the password to use for authentication.
+    pub fn password(mut self, password: Option<String>) -> Self {
+        self.password = password;
+        self
+    }
+
+    /// Set the target path to write the downloaded file to.
+    pub fn target(mut self, target: Option<PathBuf>) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Set whether to skip downloading if the target already exists.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Set whether to always extract the downloaded file as an archive.
+    pub fn extract(mut self, extract: bool) -> Self {
+        self.extract = extract;
+        self
+    }
+
     /// Invoke the download action.
+    ///
+    /// On success, the resolved path the file was written to is returned, or
+    /// the directory an archive was extracted into. `None` is returned if
+    /// the file was streamed to stdout instead.
     pub fn invoke(
         self,
-        client: &Client,
         reporter: Arc<Mutex<ProgressReporter>>,
-    ) -> Result<()> {
+    ) -> Result<Option<PathBuf>> {
+        // Only ever talk to Send over HTTPS, the resilient client built
+        // below has no knob for this in the reqwest version we're on
+        if self.file.url().scheme() != "https" {
+            return Err(DownloadError::InsecureUrl);
+        }
+
+        // Build the resilient client used for all requests in this action
+        let client = &client()
+            .map_err(|err| DownloadError::RequestError(err))?;
+
         // Create a key set for the file
         let mut key = KeySet::from(self.file);
 
@@ -59,34 +185,231 @@ impl<'a> Download<'a> {
             .map_err(|err| DownloadError::AuthError(err))?;
 
         // Fetch the meta nonce, set the input vector
-        let meta_nonce = self.fetch_meta_nonce(&client, &mut key, auth_nonce)
+        let (meta_nonce, metadata) = self.fetch_meta_nonce(&client, &mut key, auth_nonce)
             .map_err(|err| DownloadError::MetaError(err))?;
 
-        // Open the file we will write to
-        // TODO: this should become a temporary file first
-        let out = File::create("downloaded.zip")
+        // Extract the payload into a directory if the metadata marks it as
+        // an archive and the output is a directory, or if extraction was
+        // explicitly requested
+        let output_is_dir = self.target.as_ref()
+            .map(|path| path.is_dir())
+            .unwrap_or(false);
+        if self.extract || (metadata.is_archive() && output_is_dir) {
+            let dest = self.target.clone().unwrap_or_else(|| PathBuf::from("."));
+            return self.invoke_extract(client, &key, meta_nonce, dest, reporter);
+        }
+
+        // Resolve what to write the decrypted file to
+        let target = self.resolve_target(&metadata);
+
+        match target {
+            Target::Stdout => {
+                // Create the file reader, streaming from the start since
+                // stdout can not be resumed
+                let (reader, len) = self.create_file_reader(&key, meta_nonce, &client, None)?;
+
+                // `EncryptedFileWriter` decrypts into a real file, so the
+                // file is decrypted to a temporary file first; once its tag
+                // is verified, the plaintext is streamed to stdout and the
+                // temporary file is removed
+                let temp = Self::temp_path(Path::new("stdout"));
+                let out = File::create(&temp)
+                    .map_err(|err| DownloadError::FileOpenError(err))?;
+                let writer = self.create_file_writer(out, len, &key, reporter.clone())?;
+
+                if let Err(err) = self.download(reader, writer, len, reporter) {
+                    let _ = fs::remove_file(&temp);
+                    return Err(err);
+                }
+
+                let result = File::open(&temp)
+                    .map_err(|err| DownloadError::FileOpenError(err))
+                    .and_then(|mut verified| {
+                        io::copy(&mut verified, &mut io::stdout())
+                            .map(|_| ())
+                            .map_err(|err| DownloadError::StreamError(err))
+                    });
+                let _ = fs::remove_file(&temp);
+                result?;
+
+                Ok(None)
+            },
+            Target::Path(path) => {
+                // AES-GCM's authentication tag covers the entire ciphertext,
+                // and `EncryptedFileWriter` only ever starts decrypting from
+                // its beginning, so a partial download can not be resumed
+                // at the byte level without also teaching it to checkpoint
+                // and restore its cipher state, which it does not do.
+                // With `--continue`, a download that already completed is
+                // treated as done; a stale partial `.part` file is simply
+                // discarded and the download restarts from scratch.
+                if self.resume && path.is_file() {
+                    return Ok(Some(path));
+                }
+
+                // Download to a sibling temporary file first, so a failed
+                // or interrupted download never leaves a corrupt file at
+                // the target path
+                let temp = Self::temp_path(&path);
+
+                // Create the file reader, the file is always downloaded in full
+                let (reader, len) = self.create_file_reader(
+                    &key, meta_nonce, &client, Some(temp.as_path()),
+                )?;
+
+                let out = File::create(&temp)
+                    .map_err(|err| DownloadError::FileOpenError(err))?;
+
+                // Create the file writer
+                let writer = self.create_file_writer(out, len, &key, reporter.clone())?;
+
+                // Download the file, cleaning up the temporary file on failure
+                if let Err(err) = self.download(reader, writer, len, reporter) {
+                    let _ = fs::remove_file(&temp);
+                    return Err(err);
+                }
+
+                // The file has been downloaded and its tag verified, move it
+                // into place
+                fs::rename(&temp, &path)
+                    .map_err(|err| DownloadError::FileOpenError(err))?;
+
+                // TODO: return the new remote state (does it still exist remote)
+
+                Ok(Some(path))
+            },
+        }
+    }
+
+    /// Download the file as an archive, and extract its entries into the
+    /// given destination directory.
+    ///
+    /// The archive itself is downloaded to a temporary file first, which is
+    /// removed again once extraction has finished or failed.
+    fn invoke_extract(
+        &self,
+        client: &Client,
+        key: &KeySet,
+        meta_nonce: Vec<u8>,
+        dest: PathBuf,
+        reporter: Arc<Mutex<ProgressReporter>>,
+    ) -> Result<Option<PathBuf>> {
+        fs::create_dir_all(&dest)
             .map_err(|err| DownloadError::FileOpenError(err))?;
 
-        // Create the file reader for downloading
-        let (reader, len) = self.create_file_reader(&key, meta_nonce, &client);
+        // Create the file reader, the archive is always downloaded in full
+        let (reader, len) = self.create_file_reader(key, meta_nonce, client, None)?;
 
-        // Create the file writer
-        let writer = self.create_file_writer(
-            out,
-            len,
-            &key,
-            reporter.clone(),
-        );
+        // Download the archive to a temporary file inside the destination
+        let temp = Self::temp_path(&dest.join("archive.zip"));
+        let out = File::create(&temp)
+            .map_err(|err| DownloadError::FileOpenError(err))?;
+        let writer = self.create_file_writer(out, len, key, reporter.clone())?;
+
+        if let Err(err) = self.download(reader, writer, len, reporter) {
+            let _ = fs::remove_file(&temp);
+            return Err(err);
+        }
 
-        // Download the file
-        self.download(reader, writer, len, reporter);
+        let result = Self::extract_archive(&temp, &dest);
+        let _ = fs::remove_file(&temp);
+        result.map(|_| Some(dest))
+    }
 
-        // TODO: return the file path
-        // TODO: return the new remote state (does it still exist remote)
+    /// Extract all entries of the archive at `archive_path` into `dest`.
+    ///
+    /// Entries whose normalized path would escape `dest`, such as through
+    /// `..` components or an absolute path, are rejected.
+    fn extract_archive(archive_path: &Path, dest: &Path) -> Result<()> {
+        let file = File::open(archive_path)
+            .map_err(|err| DownloadError::FileOpenError(err))?;
+        let mut archive = ZipArchive::new(file)
+            .map_err(|err| DownloadError::ArchiveError(err))?;
+
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)
+                .map_err(|err| DownloadError::ArchiveError(err))?;
+
+            let name = entry.name().to_string();
+            if Self::escapes_dest(&name) {
+                return Err(DownloadError::ArchivePathTraversal);
+            }
+            let out_path = dest.join(&name);
+
+            if entry.is_dir() {
+                fs::create_dir_all(&out_path)
+                    .map_err(|err| DownloadError::FileOpenError(err))?;
+                continue;
+            }
+
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|err| DownloadError::FileOpenError(err))?;
+            }
+
+            let mut out_file = File::create(&out_path)
+                .map_err(|err| DownloadError::FileOpenError(err))?;
+            io::copy(&mut entry, &mut out_file)
+                .map_err(|err| DownloadError::StreamError(err))?;
+        }
 
         Ok(())
     }
 
+    /// Whether an archive entry name contains components, such as `..` or a
+    /// path root, that would let it escape the destination directory it is
+    /// extracted into.
+    fn escapes_dest(name: &str) -> bool {
+        Path::new(name).components().any(|component| match component {
+            Component::Normal(_) | Component::CurDir => false,
+            _ => true,
+        })
+    }
+
+    /// Build the path of the temporary file a download is written to before
+    /// it is renamed into place at `path`.
+    fn temp_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name()
+            .map(|name| name.to_os_string())
+            .unwrap_or_else(|| OsString::from("download"));
+        name.push(".part");
+        path.with_file_name(name)
+    }
+
+    /// Resolve the target to write the decrypted file to, based on the
+    /// `--output` argument and the file's original name from its metadata.
+    fn resolve_target(&self, metadata: &Metadata) -> Target {
+        match self.target {
+            // A literal `-` means the file should be streamed to stdout
+            Some(ref path) if path.to_str() == Some("-") =>
+                Target::Stdout,
+
+            // Join a directory target with the file's sanitized name
+            Some(ref path) if path.is_dir() =>
+                Target::Path(path.join(Self::sanitize_file_name(metadata.name()))),
+
+            // Use the given path as-is
+            Some(ref path) =>
+                Target::Path(path.clone()),
+
+            // Fall back to the file's sanitized name in the current directory
+            None =>
+                Target::Path(Self::sanitize_file_name(metadata.name())),
+        }
+    }
+
+    /// Reduce a file name coming from untrusted metadata to a bare file
+    /// name, discarding any directory components so it can never place the
+    /// downloaded file outside of the intended target directory.
+    ///
+    /// Falls back to a generic name if nothing safe to use as a file name
+    /// remains.
+    fn sanitize_file_name(name: &str) -> PathBuf {
+        Path::new(name).file_name()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("download"))
+    }
+
     /// Fetch the authentication nonce for the file from the Send server.
     fn fetch_auth_nonce(&self, client: &Client)
         -> StdResult<Vec<u8>, AuthError>
@@ -97,6 +420,11 @@ impl<'a> Download<'a> {
             .send()
             .map_err(|_| AuthError::NonceReqFail)?;
 
+        // The server requires a password to even hand out a nonce
+        if response.status() == StatusCode::Unauthorized {
+            return Err(AuthError::PasswordRequired);
+        }
+
         // Validate the status code
         // TODO: allow redirects here?
         if !response.status().is_success() {
@@ -120,24 +448,24 @@ impl<'a> Download<'a> {
         ).map_err(|_| AuthError::MalformedNonce)
     }
 
-    /// Fetch the metadata nonce.
+    /// Fetch the metadata nonce, along with the metadata itself.
     /// This method also sets the input vector on the given key set,
     /// extracted from the metadata.
     ///
     /// The key set, along with the authentication nonce must be given.
-    /// The meta nonce is returned.
+    /// The meta nonce and metadata are returned.
     fn fetch_meta_nonce(
         &self,
         client: &Client,
         key: &mut KeySet,
         auth_nonce: Vec<u8>,
-    ) -> StdResult<Vec<u8>, MetaError> {
+    ) -> StdResult<(Vec<u8>, Metadata), MetaError> {
         // Fetch the metadata and the nonce
         let (metadata, meta_nonce) = self.fetch_metadata(client, key, auth_nonce)?;
 
-        // Set the input vector, and return the nonce
+        // Set the input vector, and return the nonce along with the metadata
         key.set_iv(metadata.iv());
-        Ok(meta_nonce)
+        Ok((meta_nonce, metadata))
     }
 
     /// Create a metadata nonce, and fetch the metadata for the file from the
@@ -153,7 +481,9 @@ impl<'a> Download<'a> {
         auth_nonce: Vec<u8>,
     ) -> StdResult<(Metadata, Vec<u8>), MetaError> {
         // Compute the cryptographic signature for authentication
-        let sig = signature_encoded(key.auth_key().unwrap(), &auth_nonce)
+        let auth_key = self.auth_key(key)
+            .map_err(|_| MetaError::ComputeSignatureFail)?;
+        let sig = signature_encoded(&auth_key, &auth_nonce)
             .map_err(|_| MetaError::ComputeSignatureFail)?;
 
         // Buidl the request, fetch the encrypted metadata
@@ -201,69 +531,97 @@ impl<'a> Download<'a> {
     ///
     /// The response representing the file reader is returned along with the
     /// length of the reader content.
+    ///
+    /// If `target` is given, the free space on its filesystem is checked
+    /// against the file size before downloading.
     fn create_file_reader(
         &self,
         key: &KeySet,
         meta_nonce: Vec<u8>,
         client: &Client,
-    ) -> (Response, u64) {
+        target: Option<&Path>,
+    ) -> Result<(Response, u64)> {
         // Compute the cryptographic signature
         // TODO: use the metadata nonce here?
-        // TODO: do not unwrap, return an error
-        let sig = signature_encoded(key.auth_key().unwrap(), &meta_nonce)
-            .expect("failed to compute file signature");
+        let auth_key = self.auth_key(key)
+            .map_err(|err| DownloadError::AuthError(err))?;
+        let sig = signature_encoded(&auth_key, &meta_nonce)
+            .map_err(|_| DownloadError::SignatureComputeFail)?;
 
         // Build and send the download request
-        // TODO: do not unwrap here, return error
         let response = client.get(self.file.api_download_url())
             .header(Authorization(
                 format!("send-v1 {}", sig)
             ))
             .send()
-            .expect("failed to fetch file, failed to send request");
+            .map_err(|err| DownloadError::RequestError(err))?;
 
         // Validate the status code
-        // TODO: allow redirects here?
         if !response.status().is_success() {
-            // TODO: return error here
-            panic!("failed to fetch file, request status is not successful");
+            return Err(DownloadError::DownloadStatusErr(response.status()));
         }
 
-        // Get the content length
-        // TODO: make sure there is enough disk space
+        // Get the total file length
         let len = response.headers().get::<ContentLength>()
-            .expect("failed to fetch file, missing content length header")
+            .ok_or(DownloadError::MissingContentLength)?
             .0;
 
-        (response, len)
+        // Make sure there is enough disk space left to store the file
+        if let Some(target) = target {
+            Self::assert_disk_space(target, len)?;
+        }
+
+        Ok((response, len))
+    }
+
+    /// Make sure there is enough free disk space available at `target`'s
+    /// filesystem to hold `len` bytes.
+    fn assert_disk_space(target: &Path, len: u64) -> Result<()> {
+        let dir = target.parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+
+        let available = fs2::available_space(dir)
+            .map_err(|err| DownloadError::FileOpenError(err))?;
+
+        if available < len {
+            return Err(DownloadError::InsufficientDiskSpace);
+        }
+
+        Ok(())
     }
 
     /// Create a file writer.
     ///
     /// This writer will will decrypt the input on the fly, and writes the
-    /// decrypted data to the given file.
+    /// decrypted data to the given file. `EncryptedFileWriter` only
+    /// decrypts into a real file, so callers that want the result
+    /// elsewhere, such as stdout, decrypt to a temporary file first and
+    /// copy it afterwards.
     fn create_file_writer(
         &self,
-        file: File,
+        out: File,
         len: u64,
         key: &KeySet,
         reporter: Arc<Mutex<ProgressReporter>>,
-    ) -> ProgressWriter<EncryptedFileWriter> {
+    ) -> Result<ProgressWriter<EncryptedFileWriter>> {
+        let file_key = key.file_key().ok_or(DownloadError::MissingFileKey)?;
+
         // Build an encrypted writer
-        let mut writer = ProgressWriter::new(
-            EncryptedFileWriter::new(
-                file,
-                len as usize,
-                KeySet::cipher(),
-                key.file_key().unwrap(),
-                key.iv(),
-            ).expect("failed to create encrypted writer")
-        ).expect("failed to create encrypted writer");
+        let inner = EncryptedFileWriter::new(
+            out,
+            len as usize,
+            KeySet::cipher(),
+            file_key,
+            key.iv(),
+        ).map_err(|_| DownloadError::WriterInitError)?;
+        let mut writer = ProgressWriter::new(inner)
+            .map_err(|_| DownloadError::WriterInitError)?;
 
         // Set the reporter
         writer.set_reporter(reporter.clone());
 
-        writer
+        Ok(writer)
     }
 
     /// Download the file from the reader, and write it to the writer.
@@ -275,28 +633,79 @@ impl<'a> Download<'a> {
         mut writer: ProgressWriter<EncryptedFileWriter>,
         len: u64,
         reporter: Arc<Mutex<ProgressReporter>>,
-    ) {
+    ) -> Result<()> {
         // Start the writer
         reporter.lock()
             .expect("unable to start progress, failed to get lock")
             .start(len);
 
         // Write to the output file
-        io::copy(&mut reader, &mut writer)
-            .expect("failed to download and decrypt file");
+        let result = io::copy(&mut reader, &mut writer)
+            .map_err(|err| DownloadError::StreamError(err));
 
         // Finish
         reporter.lock()
             .expect("unable to finish progress, failed to get lock")
             .finish();
 
-        // Verify the writer
-        // TODO: delete the file if verification failed, show a proper error
-        assert!(writer.unwrap().verified(), "downloaded and decrypted file could not be verified");
+        // Propagate any streaming error now that the reporter has finished
+        result?;
+
+        // Verify the decrypted file against its authentication tag
+        if writer.unwrap().verified() {
+            Ok(())
+        } else {
+            Err(DownloadError::VerificationFailed)
+        }
+    }
+
+    /// Resolve the authentication key to use for signing requests.
+    ///
+    /// If a password was given, the key is derived from it using
+    /// PBKDF2-HMAC-SHA256, with the file's full URL as salt. This
+    /// matches the way Send itself protects password-enabled files, since
+    /// the auth key can then not be derived from the URL secret alone.
+    ///
+    /// Otherwise, the auth key derived from the URL secret on the key set is
+    /// used, as usual.
+    fn auth_key(&self, key: &KeySet) -> StdResult<Vec<u8>, AuthError> {
+        match self.password {
+            Some(ref password) => self.derive_password_auth_key(password),
+            None => key.auth_key()
+                .map(|key| key.to_vec())
+                .ok_or(AuthError::MissingAuthKey),
+        }
+    }
+
+    /// Derive the authentication key from the given password and this
+    /// file's URL.
+    fn derive_password_auth_key(&self, password: &str)
+        -> StdResult<Vec<u8>, AuthError>
+    {
+        let mut key = vec![0u8; PASSWORD_AUTH_KEY_LENGTH];
+        pbkdf2_hmac(
+            password.as_bytes(),
+            self.file.url().as_str().as_bytes(),
+            PASSWORD_AUTH_KEY_ITERATIONS,
+            MessageDigest::sha256(),
+            &mut key,
+        ).map_err(|_| AuthError::ComputeAuthKeyFail)?;
+
+        Ok(key)
     }
 }
 
-/// Errors that may occur in the upload action. 
+/// Where the decrypted file should be written to, as resolved from the
+/// `--output` argument and the file's metadata.
+enum Target {
+    /// Stream the decrypted file to stdout.
+    Stdout,
+
+    /// Write the decrypted file to the given path.
+    Path(PathBuf),
+}
+
+/// Errors that may occur in the download action.
 #[derive(Debug)]
 pub enum DownloadError {
     /// An authentication related error.
@@ -322,8 +731,43 @@ pub enum DownloadError {
     /// This also covers things like HTTP 404 errors.
     RequestError(ReqwestError),
 
+    /// The file's URL does not use HTTPS.
+    InsecureUrl,
+
     /// An error occurred while decoding the response data.
     DecodeError,
+
+    /// An error occurred while streaming the file to the writer.
+    StreamError(IoError),
+
+    /// The downloaded and decrypted file could not be verified against its
+    /// authentication tag. The file is likely corrupt.
+    VerificationFailed,
+
+    /// No file decryption key is available on the key set.
+    MissingFileKey,
+
+    /// Failed to construct the file writer.
+    WriterInitError,
+
+    /// Failed to compute the cryptographic signature for a download request.
+    SignatureComputeFail,
+
+    /// The download request did not return a successful status code.
+    DownloadStatusErr(StatusCode),
+
+    /// The download response is missing its `Content-Length` header.
+    MissingContentLength,
+
+    /// There is not enough free disk space to store the downloaded file.
+    InsufficientDiskSpace,
+
+    /// An error occurred while parsing or reading the downloaded archive.
+    ArchiveError(ZipError),
+
+    /// An archive entry's path would escape the destination directory it is
+    /// extracted into.
+    ArchivePathTraversal,
 }
 
 #[derive(Debug)]
@@ -334,6 +778,17 @@ pub enum AuthError {
     EmptyNonceHeader,
     MalformedNonceHeader,
     MalformedNonce,
+
+    /// The server refused to hand out an authentication nonce, answering
+    /// with HTTP 401 because the file is password protected.
+    PasswordRequired,
+
+    /// No authentication key is available to sign requests with, neither
+    /// derived from a password nor from the URL secret.
+    MissingAuthKey,
+
+    /// Failed to derive the authentication key from the given password.
+    ComputeAuthKeyFail,
 }
 
 #[derive(Debug)]
@@ -351,7 +806,7 @@ pub enum MetaError {
 
 /// The metadata response from the server, when fetching the data through
 /// the API.
-/// 
+///
 /// This metadata is required to successfully download and decrypt the
 /// corresponding file.
 #[derive(Debug, Deserialize)]
@@ -394,4 +849,50 @@ impl MetadataResponse {
                 .expect("failed to parse decrypted metadata as JSON")
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_dest_rejects_parent_dir() {
+        assert!(Download::escapes_dest("../x"));
+    }
+
+    #[test]
+    fn escapes_dest_rejects_absolute_path() {
+        assert!(Download::escapes_dest("/abs"));
+    }
+
+    #[test]
+    fn escapes_dest_allows_current_dir_component() {
+        assert!(!Download::escapes_dest("./x"));
+    }
+
+    #[test]
+    fn escapes_dest_allows_nested_path() {
+        assert!(!Download::escapes_dest("a/b"));
+    }
+
+    #[test]
+    fn temp_path_appends_part_suffix() {
+        assert_eq!(
+            Download::temp_path(Path::new("/tmp/file.zip")),
+            PathBuf::from("/tmp/file.zip.part"),
+        );
+    }
+
+    #[test]
+    fn sanitize_file_name_strips_directory_traversal() {
+        assert_eq!(
+            Download::sanitize_file_name("../../etc/passwd"),
+            PathBuf::from("passwd"),
+        );
+    }
+
+    #[test]
+    fn sanitize_file_name_falls_back_for_unsafe_name() {
+        assert_eq!(Download::sanitize_file_name(".."), PathBuf::from("download"));
+    }
+}
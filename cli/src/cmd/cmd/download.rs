@@ -20,6 +20,16 @@ impl CmdDownload {
                  .alias("out")
                  .alias("file")
                  .value_name("PATH")
-                 .help("The output file or directory"))
+                 .help("The output file or directory, '-' for stdout"))
+            .arg(Arg::with_name("continue")
+                 .long("continue")
+                 .short("c")
+                 .alias("resume")
+                 .help("Skip the download if the output file already exists; a partial download is restarted, not resumed"))
+            .arg(Arg::with_name("extract")
+                 .long("extract")
+                 .short("e")
+                 .alias("unpack")
+                 .help("Extract an archived file download"))
     }
 }